@@ -16,12 +16,50 @@ pub struct Player {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Match {
+    pub source_id: String,
     pub map: Map,
     pub team_won: Team,
     pub team_lost: Team,
     pub won_score: u32,
     pub lost_score: u32,
-    pub agents: HashMap<Player, Agent>,
+    pub player_stats: HashMap<Player, PlayerStats>,
+    pub rounds: Vec<Round>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Round {
+    pub number: u32,
+    pub winning_team: String,
+    pub winning_side: Side,
+    pub winner_buy: BuyType,
+    pub loser_buy: BuyType,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Side {
+    Attack,
+    Defense,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum BuyType {
+    Pistol,
+    Eco,
+    HalfBuy,
+    FullBuy,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlayerStats {
+    pub agent: Agent,
+    pub kills: u32,
+    pub deaths: u32,
+    pub assists: u32,
+    pub acs: f64,
+    pub adr: f64,
+    pub kast: f64,
+    pub first_kills: u32,
+    pub first_deaths: u32,
 }
 
 #[derive(Debug, Eq, Hash, PartialOrd, Ord, PartialEq, Serialize, Deserialize)]
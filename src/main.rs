@@ -1,15 +1,25 @@
 use std::collections::HashSet;
 use std::fs::read_to_string;
+use std::io::IsTerminal;
 use std::path::PathBuf;
-use std::{collections::HashMap, time::Duration, fs::File};
+use std::{collections::HashMap, fs::File};
 use std::io::Write;
 
 use log::info;
 use scraper::{Element, ElementRef, Html, Selector};
-use vlrx::{Agent, Map, Match, Player, Team};
+use vlrx::{Agent, BuyType, Map, Match, Player, PlayerStats, Round, Side, Team};
 
 use clap::{Parser, Subcommand, builder::ValueParser};
 
+mod ratings;
+use ratings::{RatingOptions, RatingsStore, DEFAULT_K};
+
+mod session;
+use session::Session;
+
+mod progress;
+use progress::Progress;
+
 fn validator_url() -> ValueParser {
     ValueParser::from(move |s: &str| -> std::result::Result<String, String> {
         let reg = regex::Regex::new(r#"https?:\/\/(www\.)?vlr\.gg\b([-a-zA-Z0-9()@:%_\+.~#?&//=]*)"#).unwrap();
@@ -42,6 +52,24 @@ enum Commands {
         event_url: String,
         #[arg(short, help = "Output file")]
         output: PathBuf,
+        #[arg(long, help = "SQLite database to persist Elo ratings into", default_value = "vctd.db")]
+        db: PathBuf,
+        #[arg(long, help = "Dataset name ratings are tracked under (defaults to the event slug)")]
+        dataset: Option<String>,
+        #[arg(long, help = "Also track per-player Elo ratings")]
+        rate_players: bool,
+        #[arg(long, help = "Elo K-factor used for rating updates", default_value_t = DEFAULT_K)]
+        k: f64,
+        #[arg(long, help = "Scale the K-factor by map margin (won_score/lost_score)")]
+        margin: bool,
+        #[arg(long, help = "Max requests per second against vlr.gg", default_value_t = 1.0)]
+        rate: f64,
+        #[arg(long, help = "Directory to cache fetched pages in, so re-running a scrape skips the network")]
+        cache_dir: Option<PathBuf>,
+        #[arg(long, help = "Max retries for 429/5xx responses before giving up", default_value_t = 3)]
+        max_retries: u32,
+        #[arg(long, help = "Suppress progress reporting (auto-suppressed for non-TTY output)")]
+        no_progress: bool,
     },
     #[command(arg_required_else_help = true, about = "Analyze scraped data")]
     Analyze {
@@ -49,7 +77,18 @@ enum Commands {
         data_path: PathBuf,
         #[command(subcommand)]
         subcmd: AnalyzeCommands,
-    }
+    },
+    #[command(arg_required_else_help = true, about = "Print the Elo leaderboard for a dataset")]
+    Ratings {
+        #[arg(help = "Dataset name (as passed to `scrape --dataset`)")]
+        dataset: String,
+        #[arg(long, help = "SQLite database ratings were persisted into", default_value = "vctd.db")]
+        db: PathBuf,
+        #[arg(long, help = "Show player ratings instead of team ratings")]
+        players: bool,
+        #[arg(long, help = "Only show the top N entries")]
+        top: Option<usize>,
+    },
 }
 #[derive(Subcommand)]
 enum AnalyzeCommands {
@@ -61,7 +100,23 @@ enum AnalyzeCommands {
         meta: bool,
         #[arg(short, long, help = "List all maps in dataset", exclusive = true)]
         list: bool,
-    }
+        #[arg(short, long, help = "Analyze composition win rate instead of pick frequency")]
+        winrate: bool,
+        #[arg(long, help = "Only show compositions with at least this many games", default_value_t = 1)]
+        min_games: usize,
+    },
+    #[command(arg_required_else_help = true, about = "analyze a player's performance")]
+    Players {
+        #[arg(help = "Player name")]
+        name: String,
+        #[arg(long, help = "Break performance down by agent played")]
+        by_agent: bool,
+    },
+    #[command(arg_required_else_help = true, about = "analyze round-by-round economy on a map")]
+    Economy {
+        #[arg(help = "Map name")]
+        map: String,
+    },
 }
 
 #[tokio::main]
@@ -73,10 +128,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let contents = read_to_string(data_path)?;
             let matches = serde_json::from_str::<Vec<Match>>(&contents)?;
             match subcmd {
-                AnalyzeCommands::Maps { map_name, meta, list } => {
+                AnalyzeCommands::Maps { map_name, meta, list, winrate, min_games } => {
                     if list {
                         let maps = get_maps(&matches);
                         println!("{:#?}", maps);
+                    } else if winrate {
+                        let map_winrate = analyze_meta_winrate(&matches, &map_name.expect("to be present w/o list"), min_games);
+                        println!("{:#?}", map_winrate);
                     } else {
                         if meta {
                             let map_meta = analyze_meta(&matches, &map_name.expect("to be present w/o list"));
@@ -84,15 +142,54 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         }
                     }
                 }
+                AnalyzeCommands::Players { name, by_agent } => {
+                    if by_agent {
+                        let breakdown = analyze_player_by_agent(&matches, &name);
+                        println!("{:#?}", breakdown);
+                    } else {
+                        let summary = analyze_player(&matches, &name);
+                        println!("{:#?}", summary);
+                    }
+                }
+                AnalyzeCommands::Economy { map } => {
+                    let report = analyze_economy(&matches, &map);
+                    println!("{:#?}", report);
+                }
             }
             Ok(())
         }
-        Commands::Scrape { event_url, output } => {
-            scrape_url(event_url, output).await
+        Commands::Scrape { event_url, output, db, dataset, rate_players, k, margin, rate, cache_dir, max_retries, no_progress } => {
+            let dataset = dataset.unwrap_or_else(|| dataset_slug(&event_url));
+            let session = Session::new(rate, cache_dir, max_retries);
+            let progress = Progress::new(no_progress || !std::io::stdout().is_terminal());
+            let matches = scrape_url(&session, &progress, event_url, output).await?;
+            progress.finish();
+            let store = RatingsStore::open(&db)?;
+            let options = RatingOptions { k, players: rate_players, use_margin: margin };
+            let synced = store.sync(&dataset, &matches, &options)?;
+            info!("Synced {} new matches into ratings dataset '{}'", synced, dataset);
+            Ok(())
+        }
+        Commands::Ratings { dataset, db, players, top } => {
+            let store = RatingsStore::open(&db)?;
+            let leaderboard = store.leaderboard(&dataset, players, top)?;
+            for (i, (name, rating)) in leaderboard.iter().enumerate() {
+                println!("{:>3}. {:<24} {:.1}", i + 1, name, rating);
+            }
+            Ok(())
         }
     }
 }
 
+fn dataset_slug(event_url: &str) -> String {
+    event_url
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or(event_url)
+        .to_owned()
+}
+
 fn get_maps(matches: &Vec<Match>) -> HashSet<Map> {
     let mut res = HashSet::new();
     for m in matches {
@@ -104,15 +201,15 @@ fn get_maps(matches: &Vec<Match>) -> HashSet<Map> {
 fn analyze_meta<'a>(matches: &'a Vec<Match>, map: &str) -> Vec<(Vec<&'a Agent>, f64)> {
     let mut map_meta: HashMap<(&Map, Vec<&Agent>), usize> = HashMap::new();
     for m in matches {
-        let mut comp1 = m.agents.iter().filter_map(|(k, v)| {
+        let mut comp1 = m.player_stats.iter().filter_map(|(k, v)| {
             if m.team_won.players.contains(&k) {
-                Some(v)
+                Some(&v.agent)
             } else { None }
         }).collect::<Vec<&Agent>>();
         comp1.sort();
-        let mut comp2 = m.agents.iter().filter_map(|(k, v)| {
+        let mut comp2 = m.player_stats.iter().filter_map(|(k, v)| {
             if m.team_lost.players.contains(&k) {
-                Some(v)
+                Some(&v.agent)
             } else { None }
         }).collect::<Vec<&Agent>>();
         comp2.sort();
@@ -128,17 +225,160 @@ fn analyze_meta<'a>(matches: &'a Vec<Match>, map: &str) -> Vec<(Vec<&'a Agent>,
     return spec_map_meta;
 }
 
-async fn scrape_url(event_url: String, output: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
-    let resp_text =
-        reqwest::get(event_url)
-            .await?
-            .text()
-            .await?;
+fn analyze_meta_winrate<'a>(
+    matches: &'a Vec<Match>,
+    map: &str,
+    min_games: usize,
+) -> Vec<(Vec<&'a Agent>, usize, f64)> {
+    let mut wins: HashMap<Vec<&Agent>, usize> = HashMap::new();
+    let mut losses: HashMap<Vec<&Agent>, usize> = HashMap::new();
+    for m in matches.iter().filter(|m| m.map.name == map) {
+        let mut comp_won = m.player_stats.iter().filter_map(|(k, v)| {
+            if m.team_won.players.contains(&k) {
+                Some(&v.agent)
+            } else { None }
+        }).collect::<Vec<&Agent>>();
+        comp_won.sort();
+        let mut comp_lost = m.player_stats.iter().filter_map(|(k, v)| {
+            if m.team_lost.players.contains(&k) {
+                Some(&v.agent)
+            } else { None }
+        }).collect::<Vec<&Agent>>();
+        comp_lost.sort();
+        *wins.entry(comp_won).or_insert(0) += 1;
+        *losses.entry(comp_lost).or_insert(0) += 1;
+    }
+
+    let mut comps = wins.keys().chain(losses.keys()).cloned().collect::<Vec<_>>();
+    comps.sort();
+    comps.dedup();
+
+    let mut result = comps
+        .into_iter()
+        .map(|comp| {
+            let w = *wins.get(&comp).unwrap_or(&0);
+            let l = *losses.get(&comp).unwrap_or(&0);
+            let games = w + l;
+            (comp, games, w as f64 / games as f64)
+        })
+        .filter(|(_, games, _)| *games >= min_games)
+        .collect::<Vec<_>>();
+    result.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap());
+    result
+}
+
+#[derive(Debug, Default)]
+struct EconomyReport {
+    total_rounds: usize,
+    attack_win_rate: f64,
+    defense_win_rate: f64,
+    eco_win_rate: f64,
+    pistol_conversion_rate: f64,
+}
+
+fn analyze_economy(matches: &Vec<Match>, map: &str) -> EconomyReport {
+    let relevant_matches = matches.iter().filter(|m| m.map.name == map).collect::<Vec<&Match>>();
+    let all_rounds = relevant_matches.iter().flat_map(|m| m.rounds.iter()).collect::<Vec<&Round>>();
+
+    let total_rounds = all_rounds.len();
+    if total_rounds == 0 {
+        return EconomyReport::default();
+    }
+
+    let attack_wins = all_rounds.iter().filter(|r| r.winning_side == Side::Attack).count();
+
+    let eco_attempts = all_rounds
+        .iter()
+        .filter(|r| r.winner_buy == BuyType::Eco || r.loser_buy == BuyType::Eco)
+        .count();
+    let eco_wins = all_rounds.iter().filter(|r| r.winner_buy == BuyType::Eco).count();
+
+    let mut pistol_attempts = 0;
+    let mut pistol_conversions = 0;
+    for m in &relevant_matches {
+        let mut rounds = m.rounds.iter().collect::<Vec<&Round>>();
+        rounds.sort_by_key(|r| r.number);
+        for pistol_number in [1, 13] {
+            let pistol = rounds.iter().find(|r| r.number == pistol_number);
+            let bonus = rounds.iter().find(|r| r.number == pistol_number + 1);
+            if let (Some(pistol), Some(bonus)) = (pistol, bonus) {
+                pistol_attempts += 1;
+                if pistol.winning_team == bonus.winning_team {
+                    pistol_conversions += 1;
+                }
+            }
+        }
+    }
+
+    EconomyReport {
+        total_rounds,
+        attack_win_rate: attack_wins as f64 / total_rounds as f64,
+        defense_win_rate: (total_rounds - attack_wins) as f64 / total_rounds as f64,
+        eco_win_rate: if eco_attempts > 0 { eco_wins as f64 / eco_attempts as f64 } else { 0.0 },
+        pistol_conversion_rate: if pistol_attempts > 0 {
+            pistol_conversions as f64 / pistol_attempts as f64
+        } else {
+            0.0
+        },
+    }
+}
+
+#[derive(Debug, Default)]
+struct PlayerSummary {
+    games: usize,
+    kills: u32,
+    deaths: u32,
+    assists: u32,
+    avg_acs: f64,
+}
+
+fn summarize_player_stats<'a>(stats: impl Iterator<Item = &'a PlayerStats>) -> PlayerSummary {
+    let stats = stats.collect::<Vec<&PlayerStats>>();
+    let games = stats.len();
+    let kills = stats.iter().map(|s| s.kills).sum();
+    let deaths = stats.iter().map(|s| s.deaths).sum();
+    let assists = stats.iter().map(|s| s.assists).sum();
+    let avg_acs = if games > 0 {
+        stats.iter().map(|s| s.acs).sum::<f64>() / games as f64
+    } else {
+        0.0
+    };
+    PlayerSummary { games, kills, deaths, assists, avg_acs }
+}
+
+fn player_stats_for<'a>(matches: &'a Vec<Match>, name: &'a str) -> impl Iterator<Item = &'a PlayerStats> {
+    matches
+        .iter()
+        .filter_map(move |m| m.player_stats.iter().find(|(p, _)| p.name == name).map(|(_, s)| s))
+}
+
+fn analyze_player(matches: &Vec<Match>, name: &str) -> PlayerSummary {
+    summarize_player_stats(player_stats_for(matches, name))
+}
+
+fn analyze_player_by_agent(matches: &Vec<Match>, name: &str) -> HashMap<String, PlayerSummary> {
+    let mut by_agent: HashMap<String, Vec<&PlayerStats>> = HashMap::new();
+    for stats in player_stats_for(matches, name) {
+        by_agent.entry(stats.agent.name.clone()).or_default().push(stats);
+    }
+    by_agent
+        .into_iter()
+        .map(|(agent, stats)| (agent, summarize_player_stats(stats.into_iter())))
+        .collect()
+}
+
+async fn scrape_url(
+    session: &Session,
+    progress: &Progress,
+    event_url: String,
+    output: PathBuf,
+) -> Result<Vec<Match>, Box<dyn std::error::Error>> {
+    progress.fetching(&event_url);
+    let resp_text = session.get(&event_url).await?;
 
     info!("Fetched initial page...");
-    tokio::time::sleep(Duration::from_secs(1)).await;
     let doc = Html::parse_document(&resp_text);
-    let mut matches = parse_event(&doc).await?;
+    let mut matches = parse_event(session, progress, &doc).await?;
 
     info!("Found matches from initial event");
     let subnav_sel = Selector::parse(".wf-subnav-item:not(.mod-active)")?;
@@ -156,23 +396,25 @@ async fn scrape_url(event_url: String, output: PathBuf) -> Result<(), Box<dyn st
 
     info!("Going to fetch following pages: {:?}", event_pages);
     for e in event_pages {
-        tokio::time::sleep(Duration::from_secs(1)).await;
-        let resp_text = reqwest::get(format!("https://vlr.gg{}", e))
-            .await?
-            .text()
-            .await?;
+        let page_url = format!("https://vlr.gg{}", e);
+        progress.fetching(&page_url);
+        let resp_text = session.get(&page_url).await?;
         let doc = Html::parse_document(&resp_text);
 
         info!("Parsing event {}", e);
-        matches.extend(parse_event(&doc).await?);
+        matches.extend(parse_event(session, progress, &doc).await?);
     }
     let serialized = serde_json::to_string(&matches)?;
     let mut f = File::create(output)?;
     f.write_all(serialized.as_bytes())?;
-    Ok(())
+    Ok(matches)
 }
 
-async fn parse_event(doc: &Html) -> Result<Vec<Match>, Box<dyn std::error::Error>> {
+async fn parse_event(
+    session: &Session,
+    progress: &Progress,
+    doc: &Html,
+) -> Result<Vec<Match>, Box<dyn std::error::Error>> {
     let mut res_matches = Vec::new();
     let sel = Selector::parse("a.bracket-item")?;
     let bracket_items = doc.select(&sel);
@@ -180,16 +422,15 @@ async fn parse_event(doc: &Html) -> Result<Vec<Match>, Box<dyn std::error::Error
         let series_url = element.value().attr("href").expect("bracket item to have link");
 
         info!("Parsing series {}", series_url);
-        let series_page = reqwest::get(format!("https://www.vlr.gg{}", series_url))
-            .await?
-            .text()
-            .await?;
-        res_matches.extend(parse_matches(&series_page)?);
-        tokio::time::sleep(Duration::from_secs(1)).await;
+        let full_url = format!("https://www.vlr.gg{}", series_url);
+        progress.fetching(&full_url);
+        let series_page = session.get(&full_url).await?;
+        res_matches.extend(parse_matches(&series_page, series_url)?);
+        progress.inc_series();
     }
     Ok(res_matches)
 }
-fn parse_matches(page_cont: &String) -> Result<Vec<Match>, Box<dyn std::error::Error>> {
+fn parse_matches(page_cont: &String, series_url: &str) -> Result<Vec<Match>, Box<dyn std::error::Error>> {
     let mut res_matches = Vec::new();
     let series_doc = Html::parse_document(page_cont);
     let match_sel = Selector::parse(".vm-stats-game")?;
@@ -197,6 +438,8 @@ fn parse_matches(page_cont: &String) -> Result<Vec<Match>, Box<dyn std::error::E
         .select(&match_sel)
         .filter(|x| x.value().attr("data-game-id") != Some("all"));
     for match_data in match_data_pages {
+        let game_id = match_data.value().attr("data-game-id").unwrap_or("0");
+        let source_id = format!("{}#{}", series_url, game_id);
         let team_selector = Selector::parse(".team")?;
         let win_selector = Selector::parse(".score.mod-win")?;
         let score_selector = Selector::parse(".score")?;
@@ -252,8 +495,16 @@ fn parse_matches(page_cont: &String) -> Result<Vec<Match>, Box<dyn std::error::E
         let row_sel = Selector::parse("tbody>tr")?;
         let player_name_sel = Selector::parse(".mod-player .text-of")?;
         let agent_sel = Selector::parse(".mod-agent img")?;
+        let kills_sel = Selector::parse(".mod-vlr-kills")?;
+        let deaths_sel = Selector::parse(".mod-vlr-deaths")?;
+        let assists_sel = Selector::parse(".mod-vlr-assists")?;
+        let acs_sel = Selector::parse(".mod-acs")?;
+        let adr_sel = Selector::parse(".mod-adr")?;
+        let kast_sel = Selector::parse(".mod-kast")?;
+        let fk_sel = Selector::parse(".mod-fk")?;
+        let fd_sel = Selector::parse(".mod-fd")?;
         let team_datas = match_data.select(&table_sel);
-        let mut agents = HashMap::new();
+        let mut player_stats = HashMap::new();
         let mut team_players: HashMap<String, Vec<String>> = HashMap::new();
 
         for (i, team_data) in team_datas.enumerate() {
@@ -285,13 +536,20 @@ fn parse_matches(page_cont: &String) -> Result<Vec<Match>, Box<dyn std::error::E
                     .attr("title")
                     .unwrap()
                     .to_owned();
-                agents.insert(name, agent);
+                let stats = PlayerStats {
+                    agent: Agent { name: agent },
+                    kills: stat_cell(&player, &kills_sel) as u32,
+                    deaths: stat_cell(&player, &deaths_sel) as u32,
+                    assists: stat_cell(&player, &assists_sel) as u32,
+                    acs: stat_cell(&player, &acs_sel),
+                    adr: stat_cell(&player, &adr_sel),
+                    kast: stat_cell(&player, &kast_sel),
+                    first_kills: stat_cell(&player, &fk_sel) as u32,
+                    first_deaths: stat_cell(&player, &fd_sel) as u32,
+                };
+                player_stats.insert(Player { name }, stats);
             }
         }
-        let agents = agents
-            .into_iter()
-            .map(|(k, v)| (Player { name: k }, Agent { name: v }))
-            .collect();
         let mut won_players = vec![];
         let mut lost_players = vec![];
         team_players.into_iter().for_each(|(t, p)| {
@@ -309,8 +567,10 @@ fn parse_matches(page_cont: &String) -> Result<Vec<Match>, Box<dyn std::error::E
             name: team_lost,
             players: lost_players,
         };
+        let rounds = parse_rounds(&match_data, &first_team, &second_team)?;
         res_matches.push(Match {
-            agents,
+            source_id,
+            player_stats,
             map: Map {
                 name: map.to_owned(),
             },
@@ -318,6 +578,7 @@ fn parse_matches(page_cont: &String) -> Result<Vec<Match>, Box<dyn std::error::E
             team_lost: lost_team_obj,
             won_score,
             lost_score,
+            rounds,
         })
     }
     Ok(res_matches)
@@ -335,3 +596,329 @@ fn parse_team_name(team_elem: &ElementRef) -> String {
         .trim()
         .to_owned()
 }
+
+fn parse_rounds(
+    match_data: &ElementRef,
+    first_team: &str,
+    second_team: &str,
+) -> Result<Vec<Round>, Box<dyn std::error::Error>> {
+    let col_sel = Selector::parse(".vlr-rounds-row-col")?;
+    let num_sel = Selector::parse(".rnd-num")?;
+    let sq_sel = Selector::parse(".rnd-sq")?;
+    let img_sel = Selector::parse("img")?;
+
+    let mut rounds = Vec::new();
+    for col in match_data.select(&col_sel) {
+        let Some(number) = col.select(&num_sel).next().and_then(|n| {
+            n.text().next()?.trim().parse::<u32>().ok()
+        }) else {
+            continue;
+        };
+
+        let squares = col.select(&sq_sel).collect::<Vec<_>>();
+        if squares.len() != 2 {
+            continue;
+        }
+        let Some(winner_idx) = squares
+            .iter()
+            .position(|sq| sq.value().attr("class").unwrap_or("").contains("mod-win"))
+        else {
+            continue;
+        };
+        let loser_idx = 1 - winner_idx;
+
+        let class = squares[winner_idx].value().attr("class").unwrap_or("");
+        let winning_side = if class.contains("mod-t") { Side::Attack } else { Side::Defense };
+        let winning_team = if winner_idx == 0 { first_team } else { second_team }.to_owned();
+
+        let buy_type_of = |sq: &ElementRef| -> BuyType {
+            if number == 1 || number == 13 {
+                return BuyType::Pistol;
+            }
+            let icon_title = sq
+                .select(&img_sel)
+                .next()
+                .and_then(|img| img.value().attr("title"))
+                .unwrap_or("")
+                .to_lowercase();
+            if icon_title.contains("eco") {
+                BuyType::Eco
+            } else if icon_title.contains("bonus") || icon_title.contains("half") {
+                BuyType::HalfBuy
+            } else {
+                BuyType::FullBuy
+            }
+        };
+        let winner_buy = buy_type_of(&squares[winner_idx]);
+        let loser_buy = buy_type_of(&squares[loser_idx]);
+
+        rounds.push(Round { number, winning_team, winning_side, winner_buy, loser_buy });
+    }
+    Ok(rounds)
+}
+
+fn stat_cell(row: &ElementRef, sel: &Selector) -> f64 {
+    let both_sel = Selector::parse(".mod-both").expect("correct selector");
+    row.select(sel)
+        .next()
+        .and_then(|cell| {
+            let value_elem = cell.select(&both_sel).next().unwrap_or(cell);
+            value_elem.text().next()
+        })
+        .map(|text| text.trim().trim_end_matches('%').parse::<f64>().unwrap_or(0.0))
+        .unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(name: &str) -> Player {
+        Player { name: name.to_owned() }
+    }
+
+    fn agent(name: &str) -> Agent {
+        Agent { name: name.to_owned() }
+    }
+
+    fn stats(agent_name: &str) -> PlayerStats {
+        PlayerStats {
+            agent: agent(agent_name),
+            kills: 0,
+            deaths: 0,
+            assists: 0,
+            acs: 0.0,
+            adr: 0.0,
+            kast: 0.0,
+            first_kills: 0,
+            first_deaths: 0,
+        }
+    }
+
+    fn match_with_comps(map: &str, won_comp: &[&str], lost_comp: &[&str]) -> Match {
+        let winners = won_comp.iter().map(|n| player(n)).collect::<Vec<_>>();
+        let losers = lost_comp.iter().map(|n| player(n)).collect::<Vec<_>>();
+
+        let mut player_stats = HashMap::new();
+        for (p, a) in winners.iter().zip(won_comp.iter()) {
+            player_stats.insert(player(&p.name), stats(a));
+        }
+        for (p, a) in losers.iter().zip(lost_comp.iter()) {
+            player_stats.insert(player(&p.name), stats(a));
+        }
+
+        Match {
+            source_id: "/series/1#1".to_owned(),
+            map: Map { name: map.to_owned() },
+            team_won: Team { name: "won".to_owned(), players: winners },
+            team_lost: Team { name: "lost".to_owned(), players: losers },
+            won_score: 13,
+            lost_score: 7,
+            player_stats,
+            rounds: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn analyze_meta_winrate_computes_wins_over_games() {
+        let matches = vec![
+            match_with_comps("Ascent", &["a1", "a2"], &["b1", "b2"]),
+            match_with_comps("Ascent", &["a1", "a2"], &["b1", "b2"]),
+            match_with_comps("Ascent", &["b1", "b2"], &["a1", "a2"]),
+        ];
+
+        let result = analyze_meta_winrate(&matches, "Ascent", 1);
+
+        let a_comp = result
+            .iter()
+            .find(|(comp, _, _)| comp.iter().any(|a| a.name == "a1"))
+            .expect("composition a1/a2 present");
+        assert_eq!(a_comp.1, 3);
+        assert!((a_comp.2 - (2.0 / 3.0)).abs() < 1e-9);
+
+        let b_comp = result
+            .iter()
+            .find(|(comp, _, _)| comp.iter().any(|a| a.name == "b1"))
+            .expect("composition b1/b2 present");
+        assert_eq!(b_comp.1, 3);
+        assert!((b_comp.2 - (1.0 / 3.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn analyze_meta_winrate_filters_by_min_games() {
+        let matches = vec![match_with_comps("Ascent", &["a1"], &["b1"])];
+
+        let result = analyze_meta_winrate(&matches, "Ascent", 2);
+
+        assert!(result.is_empty());
+    }
+
+    fn round(number: u32, winning_team: &str, side: Side, winner_buy: BuyType, loser_buy: BuyType) -> Round {
+        Round {
+            number,
+            winning_team: winning_team.to_owned(),
+            winning_side: side,
+            winner_buy,
+            loser_buy,
+        }
+    }
+
+    #[test]
+    fn analyze_economy_computes_win_rates_over_attempts() {
+        let rounds = vec![
+            round(1, "A", Side::Attack, BuyType::Pistol, BuyType::Pistol),
+            round(2, "A", Side::Defense, BuyType::FullBuy, BuyType::Eco),
+            round(3, "B", Side::Attack, BuyType::Eco, BuyType::FullBuy),
+            round(13, "B", Side::Defense, BuyType::Pistol, BuyType::Pistol),
+            round(14, "A", Side::Attack, BuyType::FullBuy, BuyType::FullBuy),
+        ];
+
+        let m = Match {
+            source_id: "/series/1#1".to_owned(),
+            map: Map { name: "Bind".to_owned() },
+            team_won: Team { name: "A".to_owned(), players: Vec::new() },
+            team_lost: Team { name: "B".to_owned(), players: Vec::new() },
+            won_score: 13,
+            lost_score: 7,
+            player_stats: HashMap::new(),
+            rounds,
+        };
+
+        let report = analyze_economy(&vec![m], "Bind");
+
+        assert_eq!(report.total_rounds, 5);
+        assert!((report.attack_win_rate - (3.0 / 5.0)).abs() < 1e-9);
+        assert!((report.defense_win_rate - (2.0 / 5.0)).abs() < 1e-9);
+        assert!((report.eco_win_rate - 0.5).abs() < 1e-9);
+        assert!((report.pistol_conversion_rate - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn analyze_economy_returns_default_when_map_has_no_rounds() {
+        let report = analyze_economy(&Vec::new(), "Icebox");
+        assert_eq!(report.total_rounds, 0);
+        assert_eq!(report.eco_win_rate, 0.0);
+        assert_eq!(report.pistol_conversion_rate, 0.0);
+    }
+
+    fn match_with_player_game(
+        source_id: &str,
+        player_name: &str,
+        agent_name: &str,
+        kills: u32,
+        deaths: u32,
+        assists: u32,
+        acs: f64,
+    ) -> Match {
+        let mut player_stats = HashMap::new();
+        player_stats.insert(
+            player(player_name),
+            PlayerStats {
+                agent: agent(agent_name),
+                kills,
+                deaths,
+                assists,
+                acs,
+                adr: 0.0,
+                kast: 0.0,
+                first_kills: 0,
+                first_deaths: 0,
+            },
+        );
+
+        Match {
+            source_id: source_id.to_owned(),
+            map: Map { name: "Ascent".to_owned() },
+            team_won: Team { name: "won".to_owned(), players: vec![player(player_name)] },
+            team_lost: Team { name: "lost".to_owned(), players: Vec::new() },
+            won_score: 13,
+            lost_score: 7,
+            player_stats,
+            rounds: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn analyze_player_sums_stats_across_matches() {
+        let matches = vec![
+            match_with_player_game("/series/1#1", "p1", "Jett", 20, 10, 5, 250.0),
+            match_with_player_game("/series/2#1", "p1", "Jett", 10, 15, 3, 150.0),
+        ];
+
+        let summary = analyze_player(&matches, "p1");
+
+        assert_eq!(summary.games, 2);
+        assert_eq!(summary.kills, 30);
+        assert_eq!(summary.deaths, 25);
+        assert_eq!(summary.assists, 8);
+        assert!((summary.avg_acs - 200.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn analyze_player_ignores_other_players() {
+        let matches = vec![match_with_player_game("/series/1#1", "p1", "Jett", 20, 10, 5, 250.0)];
+
+        let summary = analyze_player(&matches, "someone-else");
+
+        assert_eq!(summary.games, 0);
+        assert_eq!(summary.avg_acs, 0.0);
+    }
+
+    #[test]
+    fn analyze_player_by_agent_splits_by_agent() {
+        let matches = vec![
+            match_with_player_game("/series/1#1", "p1", "Jett", 20, 10, 5, 250.0),
+            match_with_player_game("/series/2#1", "p1", "Omen", 8, 12, 10, 180.0),
+        ];
+
+        let by_agent = analyze_player_by_agent(&matches, "p1");
+
+        assert_eq!(by_agent.len(), 2);
+        assert_eq!(by_agent["Jett"].games, 1);
+        assert_eq!(by_agent["Jett"].kills, 20);
+        assert_eq!(by_agent["Omen"].games, 1);
+        assert_eq!(by_agent["Omen"].kills, 8);
+    }
+
+    #[test]
+    fn stat_cell_parses_a_plain_numeric_cell() {
+        let html = Html::parse_fragment(r#"<table><tr><td class="mod-vlr-kills">22</td></tr></table>"#);
+        let sel = Selector::parse(".mod-vlr-kills").unwrap();
+        let row = html.select(&Selector::parse("tr").unwrap()).next().unwrap();
+        assert_eq!(stat_cell(&row, &sel), 22.0);
+    }
+
+    #[test]
+    fn stat_cell_prefers_the_mod_both_value_and_strips_percent() {
+        let html = Html::parse_fragment(
+            r#"<table><tr><td class="mod-kast">
+                <span class="mod-t">71%</span>
+                <span class="mod-ct">65%</span>
+                <span class="mod-both">68%</span>
+            </td></tr></table>"#,
+        );
+        let sel = Selector::parse(".mod-kast").unwrap();
+        let row = html.select(&Selector::parse("tr").unwrap()).next().unwrap();
+        assert_eq!(stat_cell(&row, &sel), 68.0);
+    }
+
+    #[test]
+    fn stat_cell_defaults_to_zero_when_missing() {
+        let html = Html::parse_fragment(r#"<table><tr></tr></table>"#);
+        let sel = Selector::parse(".mod-vlr-kills").unwrap();
+        let row = html.select(&Selector::parse("tr").unwrap()).next().unwrap();
+        assert_eq!(stat_cell(&row, &sel), 0.0);
+    }
+
+    #[test]
+    fn parse_team_name_trims_whitespace() {
+        let html = Html::parse_fragment(
+            r#"<div class="team"><div class="team-name">
+                Sentinels
+            </div></div>"#,
+        );
+        let sel = Selector::parse(".team").unwrap();
+        let team_elem = html.select(&sel).next().unwrap();
+        assert_eq!(parse_team_name(&team_elem), "Sentinels");
+    }
+}
@@ -0,0 +1,43 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+
+pub struct Progress {
+    series_bar: ProgressBar,
+    page_bar: ProgressBar,
+}
+
+impl Progress {
+    pub fn new(suppressed: bool) -> Self {
+        let multi = MultiProgress::new();
+        if suppressed {
+            multi.set_draw_target(indicatif::ProgressDrawTarget::hidden());
+        }
+
+        let spinner_style = ProgressStyle::with_template("{spinner} {msg}").unwrap();
+
+        let series_bar = multi.add(ProgressBar::new_spinner());
+        series_bar.set_style(spinner_style.clone());
+        series_bar.set_message("0 series processed");
+
+        let page_bar = multi.add(ProgressBar::new_spinner());
+        page_bar.set_style(spinner_style);
+
+        Progress { series_bar, page_bar }
+    }
+
+    pub fn fetching(&self, url: &str) {
+        self.page_bar.set_message(format!("Fetching {}", url));
+        self.page_bar.tick();
+    }
+
+    pub fn inc_series(&self) {
+        self.series_bar.inc(1);
+        self.series_bar
+            .set_message(format!("{} series processed", self.series_bar.position()));
+    }
+
+    pub fn finish(&self) {
+        self.series_bar
+            .finish_with_message(format!("{} series processed", self.series_bar.position()));
+        self.page_bar.finish_and_clear();
+    }
+}
@@ -0,0 +1,390 @@
+use std::path::Path;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use vlrx::Match;
+
+pub const DEFAULT_RATING: f64 = 1500.0;
+pub const DEFAULT_K: f64 = 32.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RatingOptions {
+    pub k: f64,
+    pub players: bool,
+    pub use_margin: bool,
+}
+
+impl Default for RatingOptions {
+    fn default() -> Self {
+        RatingOptions {
+            k: DEFAULT_K,
+            players: false,
+            use_margin: false,
+        }
+    }
+}
+
+pub struct RatingsStore {
+    conn: Connection,
+}
+
+impl RatingsStore {
+    pub fn open<P: AsRef<Path>>(path: P) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS processed_matches (
+                dataset TEXT NOT NULL,
+                match_key TEXT NOT NULL,
+                PRIMARY KEY (dataset, match_key)
+            );
+            CREATE TABLE IF NOT EXISTS team_ratings (
+                dataset TEXT NOT NULL,
+                team TEXT NOT NULL,
+                rating REAL NOT NULL,
+                PRIMARY KEY (dataset, team)
+            );
+            CREATE TABLE IF NOT EXISTS player_ratings (
+                dataset TEXT NOT NULL,
+                player TEXT NOT NULL,
+                rating REAL NOT NULL,
+                PRIMARY KEY (dataset, player)
+            );",
+        )?;
+        Ok(RatingsStore { conn })
+    }
+
+    fn is_processed(&self, dataset: &str, match_key: &str) -> rusqlite::Result<bool> {
+        let found: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT 1 FROM processed_matches WHERE dataset = ?1 AND match_key = ?2",
+                params![dataset, match_key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(found.is_some())
+    }
+
+    fn mark_processed(&self, dataset: &str, match_key: &str) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO processed_matches (dataset, match_key) VALUES (?1, ?2)",
+            params![dataset, match_key],
+        )?;
+        Ok(())
+    }
+
+    fn team_rating(&self, dataset: &str, team: &str) -> rusqlite::Result<f64> {
+        let rating: Option<f64> = self
+            .conn
+            .query_row(
+                "SELECT rating FROM team_ratings WHERE dataset = ?1 AND team = ?2",
+                params![dataset, team],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(rating.unwrap_or(DEFAULT_RATING))
+    }
+
+    fn set_team_rating(&self, dataset: &str, team: &str, rating: f64) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO team_ratings (dataset, team, rating) VALUES (?1, ?2, ?3)
+             ON CONFLICT(dataset, team) DO UPDATE SET rating = excluded.rating",
+            params![dataset, team, rating],
+        )?;
+        Ok(())
+    }
+
+    fn player_rating(&self, dataset: &str, player: &str) -> rusqlite::Result<f64> {
+        let rating: Option<f64> = self
+            .conn
+            .query_row(
+                "SELECT rating FROM player_ratings WHERE dataset = ?1 AND player = ?2",
+                params![dataset, player],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(rating.unwrap_or(DEFAULT_RATING))
+    }
+
+    fn set_player_rating(&self, dataset: &str, player: &str, rating: f64) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO player_ratings (dataset, player, rating) VALUES (?1, ?2, ?3)
+             ON CONFLICT(dataset, player) DO UPDATE SET rating = excluded.rating",
+            params![dataset, player, rating],
+        )?;
+        Ok(())
+    }
+
+    pub fn sync(
+        &self,
+        dataset: &str,
+        matches: &[Match],
+        options: &RatingOptions,
+    ) -> rusqlite::Result<usize> {
+        let mut newly_processed = 0;
+        for m in matches {
+            let key = match_key(m);
+            if self.is_processed(dataset, key)? {
+                continue;
+            }
+            self.apply_match(dataset, m, options)?;
+            self.mark_processed(dataset, key)?;
+            newly_processed += 1;
+        }
+        Ok(newly_processed)
+    }
+
+    fn apply_match(&self, dataset: &str, m: &Match, options: &RatingOptions) -> rusqlite::Result<()> {
+        let k = options.k * margin_multiplier(m, options.use_margin);
+
+        let ra = self.team_rating(dataset, &m.team_won.name)?;
+        let rb = self.team_rating(dataset, &m.team_lost.name)?;
+        let (new_ra, new_rb) = update_elo(ra, rb, 1.0, k);
+        self.set_team_rating(dataset, &m.team_won.name, new_ra)?;
+        self.set_team_rating(dataset, &m.team_lost.name, new_rb)?;
+
+        if options.players {
+            let lost_avg = self.average_player_rating(dataset, &m.team_lost)?;
+            let won_avg = self.average_player_rating(dataset, &m.team_won)?;
+            for p in &m.team_won.players {
+                let rp = self.player_rating(dataset, &p.name)?;
+                let (new_rp, _) = update_elo(rp, lost_avg, 1.0, k);
+                self.set_player_rating(dataset, &p.name, new_rp)?;
+            }
+            for p in &m.team_lost.players {
+                let rp = self.player_rating(dataset, &p.name)?;
+                let (new_rp, _) = update_elo(rp, won_avg, 0.0, k);
+                self.set_player_rating(dataset, &p.name, new_rp)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn average_player_rating(&self, dataset: &str, team: &vlrx::Team) -> rusqlite::Result<f64> {
+        if team.players.is_empty() {
+            return Ok(DEFAULT_RATING);
+        }
+        let mut total = 0.0;
+        for p in &team.players {
+            total += self.player_rating(dataset, &p.name)?;
+        }
+        Ok(total / team.players.len() as f64)
+    }
+
+    pub fn leaderboard(
+        &self,
+        dataset: &str,
+        players: bool,
+        top: Option<usize>,
+    ) -> rusqlite::Result<Vec<(String, f64)>> {
+        let query = if players {
+            "SELECT player, rating FROM player_ratings WHERE dataset = ?1 ORDER BY rating DESC"
+        } else {
+            "SELECT team, rating FROM team_ratings WHERE dataset = ?1 ORDER BY rating DESC"
+        };
+        let mut stmt = self.conn.prepare(query)?;
+        let rows = stmt
+            .query_map(params![dataset], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<(String, f64)>>>()?;
+        match top {
+            Some(n) => Ok(rows.into_iter().take(n).collect()),
+            None => Ok(rows),
+        }
+    }
+}
+
+pub fn expected_score(ra: f64, rb: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((rb - ra) / 400.0))
+}
+
+pub fn update_elo(ra: f64, rb: f64, score_a: f64, k: f64) -> (f64, f64) {
+    let ea = expected_score(ra, rb);
+    let eb = 1.0 - ea;
+    let score_b = 1.0 - score_a;
+    (ra + k * (score_a - ea), rb + k * (score_b - eb))
+}
+
+fn match_key(m: &Match) -> &str {
+    &m.source_id
+}
+
+fn margin_multiplier(m: &Match, enabled: bool) -> f64 {
+    if !enabled {
+        return 1.0;
+    }
+    let total = (m.won_score + m.lost_score) as f64;
+    if total == 0.0 {
+        return 1.0;
+    }
+    1.0 + (m.won_score as f64 - m.lost_score as f64) / total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vlrx::Team;
+
+    #[test]
+    fn expected_score_is_half_for_equal_ratings() {
+        assert!((expected_score(1500.0, 1500.0) - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn expected_score_favors_higher_rating() {
+        let e = expected_score(1600.0, 1400.0);
+        assert!((e - 0.7597469266).abs() < 1e-6);
+    }
+
+    #[test]
+    fn update_elo_rewards_the_winner_and_penalizes_the_loser() {
+        let (new_ra, new_rb) = update_elo(1500.0, 1500.0, 1.0, 32.0);
+        assert!((new_ra - 1516.0).abs() < 1e-9);
+        assert!((new_rb - 1484.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn update_elo_moves_less_on_an_expected_win() {
+        let (new_ra, _) = update_elo(1600.0, 1400.0, 1.0, 32.0);
+        assert!(new_ra - 1600.0 < 16.0);
+    }
+
+    fn test_match(source_id: &str, won_score: u32, lost_score: u32) -> Match {
+        Match {
+            source_id: source_id.to_owned(),
+            map: vlrx::Map { name: "Bind".to_owned() },
+            team_won: Team { name: "A".to_owned(), players: Vec::new() },
+            team_lost: Team { name: "B".to_owned(), players: Vec::new() },
+            won_score,
+            lost_score,
+            player_stats: std::collections::HashMap::new(),
+            rounds: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn margin_multiplier_is_one_when_disabled() {
+        let m = test_match("/series/1#137", 13, 2);
+        assert_eq!(margin_multiplier(&m, false), 1.0);
+    }
+
+    #[test]
+    fn margin_multiplier_scales_with_score_differential() {
+        let m = test_match("/series/1#137", 13, 2);
+        let expected = 1.0 + (13.0 - 2.0) / 15.0;
+        assert!((margin_multiplier(&m, true) - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn margin_multiplier_is_one_for_an_even_match() {
+        let m = test_match("/series/1#137", 13, 13);
+        assert_eq!(margin_multiplier(&m, true), 1.0);
+    }
+
+    #[test]
+    fn match_key_is_stable_and_distinguishes_same_scoreline_matches() {
+        let a = test_match("/series/1#137", 13, 7);
+        let b = test_match("/series/1#137", 13, 7);
+        let c = test_match("/series/2#137", 13, 7);
+        assert_eq!(match_key(&a), match_key(&b));
+        assert_ne!(match_key(&a), match_key(&c));
+    }
+
+    fn store_in_memory() -> RatingsStore {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS processed_matches (
+                dataset TEXT NOT NULL,
+                match_key TEXT NOT NULL,
+                PRIMARY KEY (dataset, match_key)
+            );
+            CREATE TABLE IF NOT EXISTS team_ratings (
+                dataset TEXT NOT NULL,
+                team TEXT NOT NULL,
+                rating REAL NOT NULL,
+                PRIMARY KEY (dataset, team)
+            );
+            CREATE TABLE IF NOT EXISTS player_ratings (
+                dataset TEXT NOT NULL,
+                player TEXT NOT NULL,
+                rating REAL NOT NULL,
+                PRIMARY KEY (dataset, player)
+            );",
+        )
+        .unwrap();
+        RatingsStore { conn }
+    }
+
+    #[test]
+    fn sync_skips_matches_it_has_already_processed() {
+        let store = store_in_memory();
+        let m = test_match("/series/1#137", 13, 7);
+        let options = RatingOptions::default();
+
+        let first = store.sync("ds", std::slice::from_ref(&m), &options).unwrap();
+        let second = store.sync("ds", std::slice::from_ref(&m), &options).unwrap();
+
+        assert_eq!(first, 1);
+        assert_eq!(second, 0);
+    }
+
+    #[test]
+    fn sync_applies_matches_with_an_identical_scoreline_but_distinct_source() {
+        let store = store_in_memory();
+        let a = test_match("/series/1#137", 13, 7);
+        let b = test_match("/series/2#137", 13, 7);
+        let options = RatingOptions::default();
+
+        let processed = store.sync("ds", &[a, b], &options).unwrap();
+
+        assert_eq!(processed, 2);
+    }
+
+    #[test]
+    fn apply_match_updates_team_ratings_by_k() {
+        let store = store_in_memory();
+        let m = test_match("/series/1#137", 13, 7);
+        let options = RatingOptions { k: 32.0, players: false, use_margin: false };
+
+        store.apply_match("ds", &m, &options).unwrap();
+
+        assert!((store.team_rating("ds", "A").unwrap() - 1516.0).abs() < 1e-9);
+        assert!((store.team_rating("ds", "B").unwrap() - 1484.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn apply_match_scores_the_losing_team_against_the_winners_pre_match_average() {
+        let store = store_in_memory();
+        let mut m = test_match("/series/1#137", 13, 7);
+        m.team_won.players = vec![vlrx::Player { name: "star".to_owned() }];
+        m.team_lost.players = vec![vlrx::Player { name: "scrub".to_owned() }];
+        store.set_player_rating("ds", "star", 1800.0).unwrap();
+        let options = RatingOptions { k: 32.0, players: true, use_margin: false };
+
+        store.apply_match("ds", &m, &options).unwrap();
+
+        let (expected_scrub, _) = update_elo(DEFAULT_RATING, 1800.0, 0.0, 32.0);
+        assert!((store.player_rating("ds", "scrub").unwrap() - expected_scrub).abs() < 1e-9);
+    }
+
+    #[test]
+    fn leaderboard_orders_teams_by_rating_descending() {
+        let store = store_in_memory();
+        store.set_team_rating("ds", "A", 1600.0).unwrap();
+        store.set_team_rating("ds", "B", 1400.0).unwrap();
+
+        let board = store.leaderboard("ds", false, None).unwrap();
+
+        assert_eq!(board, vec![("A".to_owned(), 1600.0), ("B".to_owned(), 1400.0)]);
+    }
+
+    #[test]
+    fn leaderboard_respects_top_n() {
+        let store = store_in_memory();
+        store.set_team_rating("ds", "A", 1600.0).unwrap();
+        store.set_team_rating("ds", "B", 1400.0).unwrap();
+
+        let board = store.leaderboard("ds", false, Some(1)).unwrap();
+
+        assert_eq!(board, vec![("A".to_owned(), 1600.0)]);
+    }
+}
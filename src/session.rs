@@ -0,0 +1,203 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use log::{debug, warn};
+use reqwest::StatusCode;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+struct TokenBucket {
+    capacity: f64,
+    refill_rate: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: f64, refill_rate: f64) -> Self {
+        TokenBucket {
+            capacity,
+            refill_rate,
+            tokens: capacity,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_rate).min(self.capacity);
+        self.last_refill = now;
+    }
+}
+
+pub struct Session {
+    client: reqwest::Client,
+    bucket: Option<Mutex<TokenBucket>>,
+    max_retries: u32,
+    cache_dir: Option<PathBuf>,
+}
+
+impl Session {
+    pub fn new(rate: f64, cache_dir: Option<PathBuf>, max_retries: u32) -> Self {
+        let bucket = if rate > 0.0 {
+            Some(Mutex::new(TokenBucket::new(rate.max(1.0), rate)))
+        } else {
+            None
+        };
+        if let Some(dir) = &cache_dir {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        Session {
+            client: reqwest::Client::new(),
+            bucket,
+            max_retries,
+            cache_dir,
+        }
+    }
+
+    pub async fn get(&self, url: &str) -> Result<String, Box<dyn std::error::Error>> {
+        if let Some(cached) = self.read_cache(url) {
+            debug!("Cache hit for {}", url);
+            return Ok(cached);
+        }
+
+        self.throttle().await;
+
+        let mut attempt = 0;
+        loop {
+            let resp = self.client.get(url).send().await?;
+            let status = resp.status();
+            if status.is_success() {
+                let body = resp.text().await?;
+                self.write_cache(url, &body);
+                return Ok(body);
+            }
+            if attempt >= self.max_retries || !is_retryable(status) {
+                return Err(format!("request to {} failed with status {}", url, status).into());
+            }
+            let backoff = Duration::from_secs_f64(2f64.powi(attempt as i32));
+            warn!(
+                "Request to {} failed with {}, retrying in {:?} (attempt {}/{})",
+                url, status, backoff, attempt + 1, self.max_retries
+            );
+            tokio::time::sleep(backoff).await;
+            attempt += 1;
+            self.throttle().await;
+        }
+    }
+
+    async fn throttle(&self) {
+        let Some(bucket) = &self.bucket else {
+            return;
+        };
+        loop {
+            let wait = {
+                let mut bucket = bucket.lock().await;
+                bucket.refill();
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    None
+                } else {
+                    Some(Duration::from_secs_f64((1.0 - bucket.tokens) / bucket.refill_rate))
+                }
+            };
+            match wait {
+                None => return,
+                Some(d) => tokio::time::sleep(d).await,
+            }
+        }
+    }
+
+    fn cache_path(&self, url: &str) -> Option<PathBuf> {
+        let dir = self.cache_dir.as_ref()?;
+        Some(dir.join(cache_key(url)))
+    }
+
+    fn read_cache(&self, url: &str) -> Option<String> {
+        let path = self.cache_path(url)?;
+        std::fs::read_to_string(path).ok()
+    }
+
+    fn write_cache(&self, url: &str, body: &str) {
+        if let Some(path) = self.cache_path(url) {
+            let _ = std::fs::write(path, body);
+        }
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+fn cache_key(url: &str) -> String {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    url.hash(&mut hasher);
+    format!("{:016x}.html", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_bucket_starts_full() {
+        let bucket = TokenBucket::new(5.0, 2.0);
+        assert_eq!(bucket.tokens, 5.0);
+    }
+
+    #[test]
+    fn token_bucket_refills_over_elapsed_time() {
+        let mut bucket = TokenBucket::new(5.0, 2.0);
+        bucket.tokens = 0.0;
+        bucket.last_refill = Instant::now() - Duration::from_secs(2);
+
+        bucket.refill();
+
+        assert!((bucket.tokens - 4.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn token_bucket_refill_is_capped_at_capacity() {
+        let mut bucket = TokenBucket::new(5.0, 2.0);
+        bucket.tokens = 0.0;
+        bucket.last_refill = Instant::now() - Duration::from_secs(60);
+
+        bucket.refill();
+
+        assert_eq!(bucket.tokens, 5.0);
+    }
+
+    #[test]
+    fn session_with_zero_rate_has_no_bucket() {
+        let session = Session::new(0.0, None, 0);
+        assert!(session.bucket.is_none());
+    }
+
+    #[test]
+    fn session_with_positive_rate_has_a_full_bucket() {
+        let session = Session::new(5.0, None, 0);
+        let bucket = session.bucket.unwrap().into_inner();
+        assert_eq!(bucket.capacity, 5.0);
+        assert_eq!(bucket.tokens, 5.0);
+    }
+
+    #[test]
+    fn is_retryable_covers_rate_limit_and_server_errors() {
+        assert!(is_retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(!is_retryable(StatusCode::NOT_FOUND));
+        assert!(!is_retryable(StatusCode::OK));
+    }
+
+    #[test]
+    fn cache_key_is_stable_and_distinguishes_urls() {
+        let a = cache_key("https://vlr.gg/123");
+        let b = cache_key("https://vlr.gg/123");
+        let c = cache_key("https://vlr.gg/456");
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert!(a.ends_with(".html"));
+    }
+}